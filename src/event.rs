@@ -0,0 +1,24 @@
+//! A single event stream unifying child output, terminal resizes, the
+//! redraw clock, and child exit, so `print` is one loop matching on
+//! `Event` instead of juggling an output channel, a redraw timeout, and
+//! `child.wait()` separately.
+
+use std::process::ExitCode;
+
+#[derive(Debug)]
+pub enum Event {
+    /// A raw chunk of output text, from the child or from stdin. Not
+    /// necessarily a full line: it's whatever bytes a reader had
+    /// available, so a bare `\r`-repainted progress line arrives as its
+    /// own chunk instead of waiting for a trailing newline.
+    Line(String),
+    /// The terminal is (now) `(cols, rows)`, either from the initial
+    /// size probe or a SIGWINCH.
+    Resize(u16, u16),
+    /// A redraw clock tick.
+    Tick,
+    /// The child process has exited.
+    ChildExit(ExitCode),
+    /// All output readers reached end of file.
+    Eof,
+}
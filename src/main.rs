@@ -1,18 +1,29 @@
 #![doc = include_str!("../README.md")]
 
+mod event;
+mod pty;
+mod vt;
+
 use clap::{CommandFactory, Parser};
+use event::Event;
 use std::{
-    collections::VecDeque,
     fmt,
-    io::{BufRead, BufReader, IsTerminal},
-    process::{Command, ExitCode, Stdio},
-    sync::mpsc::{self},
+    io::{BufRead, BufReader, BufWriter, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitCode, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self},
+        Arc,
+    },
     thread,
     time::Instant,
 };
 
 /// Run a command and display its output in a scrolling window.
-/// Doesn't particularly work well with commands outputing control characters.
+/// Understands carriage returns, cursor moves, line erases, and colors,
+/// so progress bars and colored output render the way they would in a
+/// real terminal.
 #[derive(Debug, Parser)]
 #[clap(
     version = env!("PKG_VERSION"),
@@ -22,36 +33,51 @@ use std::{
     help_template = HELP,
 )]
 struct Opt {
-    /// The command to run. Will be run through a shell.
+    /// The command(s) to run. Will be run through a shell. Repeat to
+    /// queue several commands, run one after another, with a summary
+    /// table printed once they've all finished.
     #[clap(value_hint = clap::ValueHint::CommandString)]
-    command: Option<String>,
+    command: Vec<String>,
+    /// Alternative to the positional command(s), can be repeated
+    #[clap(short = 'c', long = "command", value_hint = clap::ValueHint::CommandString)]
+    extra_commands: Vec<String>,
     /// Number of lines to display at a time
     #[clap(short, long)]
     num_lines: Option<usize>,
     /// Print autocompletion script for your shell
     #[arg(long = "generate", value_enum)]
     generator: Option<clap_complete::Shell>,
+    /// Run the command on a pseudo-terminal so it keeps color, spinners,
+    /// and line-buffered progress output
+    #[clap(long)]
+    pty: bool,
+    /// Also write every line of output to this file, so the full log
+    /// survives after it has scrolled out of the window
+    #[clap(long, value_hint = clap::ValueHint::FilePath)]
+    tee: Option<PathBuf>,
+    /// Replay a log captured with --tee through the scrolling window,
+    /// instead of running a command
+    #[clap(long, value_hint = clap::ValueHint::FilePath, conflicts_with_all = ["command", "extra_commands"])]
+    replay: Option<PathBuf>,
+    /// Lines per second to feed during --replay
+    #[clap(long, default_value_t = 10.0, requires = "replay")]
+    replay_speed: f64,
 }
 
 impl Opt {
-    fn num_lines(&self) -> Option<usize> {
-        use std::sync::atomic::{AtomicU16, AtomicU8, Ordering};
-        static CALLED: AtomicU8 = AtomicU8::new(0);
-        static ROWS: AtomicU16 = AtomicU16::new(0);
-        if let Some(i) = self.num_lines {
-            return Some(i);
-        }
-        let rows = if CALLED.load(Ordering::Relaxed) == 0 {
-            let term = termsize::get()?;
-            ROWS.store(term.rows, Ordering::Relaxed);
-            term.rows
-        } else {
-            ROWS.load(Ordering::Relaxed)
-        };
-        if CALLED.fetch_add(1, Ordering::Relaxed) == 10 {
-            CALLED.store(0, Ordering::Relaxed);
-        }
-        Some(num_lines_heuristic(rows).into())
+    /// The number of lines to display, given the terminal's current
+    /// height. Honors the explicit `--num-lines` override; otherwise
+    /// derives it from `rows` via [`num_lines_heuristic`].
+    fn num_lines(&self, rows: u16) -> usize {
+        self.num_lines.unwrap_or_else(|| num_lines_heuristic(rows).into())
+    }
+
+    /// The queued commands, positional arguments first.
+    fn commands(&self) -> impl Iterator<Item = &str> {
+        self.command
+            .iter()
+            .chain(self.extra_commands.iter())
+            .map(String::as_str)
     }
 }
 
@@ -63,114 +89,455 @@ fn num_lines_heuristic(rows: u16) -> u16 {
     }
 }
 
+/// Switches the terminal to the alternate screen buffer and hides the
+/// cursor for as long as it stays alive, restoring both on drop.
+///
+/// A no-op when stdout isn't a terminal, so piped output keeps the
+/// current plain-clearing behaviour.
+struct AltScreen {
+    enabled: bool,
+}
+
+impl AltScreen {
+    fn enter() -> Self {
+        let enabled = std::io::stdout().is_terminal();
+        if enabled {
+            print!("\x1B[?1049h\x1B[?25l");
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+        }
+        Self { enabled }
+    }
+
+    /// Leaves the alternate screen buffer and shows the cursor again.
+    /// Safe to call from a signal handler or panic hook, and a no-op if
+    /// stdout isn't a terminal (e.g. output is piped): unlike `enter()`,
+    /// there's no `&self` to check here since the panic hook and Ctrl-C
+    /// handler call it without ever having constructed an `AltScreen`.
+    ///
+    /// Writes straight to the raw fd instead of through `Stdout`: the
+    /// `ctrlc` handler runs on its own thread, the panic hook on
+    /// whichever thread panicked, and `print()`'s event loop holds `Stdout`'s internal lock for its
+    /// entire run, so going through `print!`/`Write` here would block
+    /// until that loop finishes on its own — exactly what Ctrl-C is
+    /// supposed to interrupt.
+    fn leave() {
+        if std::io::stdout().is_terminal() {
+            let sequence = b"\x1B[?1049l\x1B[?25h";
+            // SAFETY: fd 1 is stdout, `sequence` is a valid buffer for
+            // the length passed, and a short write is harmless here (it
+            // just leaves a partial escape sequence, not a crash).
+            unsafe {
+                libc::write(1, sequence.as_ptr().cast(), sequence.len());
+            }
+        }
+    }
+}
+
+impl Drop for AltScreen {
+    fn drop(&mut self) {
+        if self.enabled {
+            Self::leave();
+        }
+    }
+}
+
+/// Makes sure the alternate screen buffer is left even when we panic
+/// mid-draw, then defers to the default hook for the actual report.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        AltScreen::leave();
+        default_hook(info);
+    }));
+}
+
+/// Current terminal size as `(cols, rows)`, falling back to 80x24.
+fn terminal_size() -> (u16, u16) {
+    termsize::get().map_or((80, 24), |t| (t.cols, t.rows))
+}
+
+/// Spawns a SIGWINCH watcher that feeds `Event::Resize` into `tx`, once
+/// immediately for the initial size probe and then once per resize.
+fn spawn_resize_events(tx: mpsc::Sender<Event>) -> anyhow::Result<()> {
+    let (cols, rows) = terminal_size();
+    let _ = tx.send(Event::Resize(cols, rows));
+    let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGWINCH])?;
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            let (cols, rows) = terminal_size();
+            if tx.send(Event::Resize(cols, rows)).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Spawns the redraw clock: an `Event::Tick` every 100ms until `tx`'s
+/// receiver is dropped.
+fn spawn_tick_clock(tx: mpsc::Sender<Event>) {
+    const DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+    thread::spawn(move || loop {
+        thread::sleep(DELAY);
+        if tx.send(Event::Tick).is_err() {
+            break;
+        }
+    });
+}
+
 fn main() -> anyhow::Result<ExitCode> {
     let opt = Opt::parse();
     if print_completions(opt.generator) {
         return Ok(ExitCode::SUCCESS);
     }
-    let code = thread::scope(|s| -> anyhow::Result<_> {
+    install_panic_hook();
+    ctrlc::set_handler(|| {
+        AltScreen::leave();
+        std::process::exit(130);
+    })?;
+    truncate_tee(&opt);
+    if let Some(path) = &opt.replay {
+        return run_replay(path, &opt);
+    }
+    let commands: Vec<&str> = opt.commands().collect();
+    if !commands.is_empty() {
+        return run_batch(&commands, &opt);
+    }
+    thread::scope(|s| -> anyhow::Result<ExitCode> {
         let (sender, receiver) = mpsc::channel();
-        if let Some(cmd) = &opt.command {
-            let mut child = Command::new("bash")
-                .arg("--norc")
-                .arg("-c")
-                .arg(cmd)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()?;
+        spawn_resize_events(sender.clone())?;
+        spawn_tick_clock(sender.clone());
+        if !std::io::stdin().is_terminal() {
+            let remaining = Arc::new(AtomicUsize::new(1));
             s.spawn({
-                let stdout = child.stdout.take().unwrap();
                 let sender = sender.clone();
-                move || read(stdout, sender)
-            });
-            let stdout = s.spawn({
-                let stderr = child.stderr.take().unwrap();
-                move || read(stderr, sender)
+                move || read(std::io::stdin(), sender, remaining)
             });
-            let stderr = s.spawn(move || {
-                let stdout = std::io::stdout();
-                print(stdout.lock(), receiver, opt);
-            });
-            let status = child.wait()?;
-            let _ = stdout.join();
-            let _ = stderr.join();
-            return Ok(status
-                .code()
-                .and_then(|i| u8::try_from(i).ok())
-                .map(ExitCode::from)
-                .unwrap_or(if status.success() {
-                    ExitCode::SUCCESS
-                } else {
-                    ExitCode::FAILURE
-                }));
+            drop(sender);
+            let stdout = std::io::stdout();
+            return Ok(print(stdout.lock(), receiver, &opt, false, None, None));
         }
-        if !std::io::stdin().is_terminal() {
-            let stdin = s.spawn(move || read(std::io::stdin(), sender));
-            let h = s.spawn(move || {
-                let stdout = std::io::stdout();
-                print(stdout.lock(), receiver, opt)
+        Ok(ExitCode::SUCCESS)
+    })
+}
+
+/// Truncates the `--tee` destination once, up front, regardless of
+/// which of `run_replay`/`run_batch`/the piped-stdin path ends up
+/// running `print()`. Each `print()` call's `open_tee` then reopens the
+/// file in append mode, so a batch's later commands don't clobber its
+/// earlier ones.
+///
+/// Skipped when `--tee` and `--replay` point at the same path: that's a
+/// "replay and re-save" invocation, and truncating up front would wipe
+/// the very log `run_replay` is about to read from before it gets the
+/// chance to.
+fn truncate_tee(opt: &Opt) {
+    if opt.tee.is_some() && opt.tee == opt.replay {
+        return;
+    }
+    if let Some(path) = &opt.tee {
+        let _ = std::fs::File::create(path);
+    }
+}
+
+/// Runs each command in `commands` in turn in the scrolling window, then
+/// prints a summary table of exit status and duration for the whole
+/// batch, like advent-of-code's `run_multi`.
+///
+/// A single command skips the `[1/1]` label and the summary table
+/// entirely, so `scrollrun "cmd"` still looks the way it did before
+/// multi-command batches existed.
+fn run_batch(commands: &[&str], opt: &Opt) -> anyhow::Result<ExitCode> {
+    let batch_start = Instant::now();
+    let mut results = Vec::with_capacity(commands.len());
+    let mut codes = Vec::with_capacity(commands.len());
+    for (i, cmd) in commands.iter().enumerate() {
+        let label = (commands.len() > 1).then(|| format!("[{}/{}] {cmd}", i + 1, commands.len()));
+        let start = Instant::now();
+        let (code, success) = run_command(cmd, opt, label.as_deref())?;
+        results.push((*cmd, success, start.elapsed()));
+        codes.push((success, code));
+    }
+    if commands.len() > 1 {
+        print_summary(&mut std::io::stdout(), &results, batch_start.elapsed());
+    }
+    Ok(batch_exit_code(&codes))
+}
+
+/// The process exit code for a whole batch: the first command's code
+/// that failed, or the last command's code if every command succeeded.
+/// A later command succeeding shouldn't hide an earlier failure.
+fn batch_exit_code(codes: &[(bool, ExitCode)]) -> ExitCode {
+    codes
+        .iter()
+        .find(|(success, _)| !success)
+        .or_else(|| codes.last())
+        .map_or(ExitCode::SUCCESS, |&(_, code)| code)
+}
+
+/// Runs one command to completion in the scrolling window, returning its
+/// exit code for the process and whether it succeeded for the summary
+/// table (`ExitCode` itself doesn't expose that).
+fn run_command(cmd: &str, opt: &Opt, label: Option<&str>) -> anyhow::Result<(ExitCode, bool)> {
+    let exit_status: Arc<std::sync::OnceLock<ExitStatus>> = Arc::new(std::sync::OnceLock::new());
+    let code = thread::scope(|s| -> anyhow::Result<ExitCode> {
+        let (sender, receiver) = mpsc::channel();
+        spawn_resize_events(sender.clone())?;
+        spawn_tick_clock(sender.clone());
+        if opt.pty {
+            let mut pty_child = pty::PtyChild::spawn(cmd, pty::current_winsize())?;
+            let resize_target = pty_child.master()?;
+            let remaining = Arc::new(AtomicUsize::new(1));
+            s.spawn({
+                let master = pty_child.master()?;
+                let sender = sender.clone();
+                move || read(master, sender, remaining)
             });
-            let _ = stdin.join();
-            h.join()
-                .map_err(|_| anyhow::anyhow!("couldn't read from pipe"))?;
+            s.spawn({
+                let sender = sender.clone();
+                let exit_status = exit_status.clone();
+                move || {
+                    let status = pty_child.wait();
+                    if let Ok(status) = status {
+                        let _ = exit_status.set(status);
+                    }
+                    let code = status.map(exit_code_from_status).unwrap_or(ExitCode::FAILURE);
+                    let _ = sender.send(Event::ChildExit(code));
+                }
+            });
+            drop(sender);
+            let stdout = std::io::stdout();
+            return Ok(print(stdout.lock(), receiver, opt, true, label, Some(&resize_target)));
         }
-        Ok(ExitCode::SUCCESS)
+        let mut child = Command::new("bash")
+            .arg("--norc")
+            .arg("-c")
+            .arg(cmd)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let remaining = Arc::new(AtomicUsize::new(2));
+        s.spawn({
+            let stdout = child.stdout.take().unwrap();
+            let sender = sender.clone();
+            let remaining = remaining.clone();
+            move || read(stdout, sender, remaining)
+        });
+        s.spawn({
+            let stderr = child.stderr.take().unwrap();
+            let sender = sender.clone();
+            move || read(stderr, sender, remaining)
+        });
+        s.spawn({
+            let sender = sender.clone();
+            let exit_status = exit_status.clone();
+            move || {
+                let status = child.wait();
+                if let Ok(status) = status {
+                    let _ = exit_status.set(status);
+                }
+                let code = status.map(exit_code_from_status).unwrap_or(ExitCode::FAILURE);
+                let _ = sender.send(Event::ChildExit(code));
+            }
+        });
+        drop(sender);
+        let stdout = std::io::stdout();
+        Ok(print(stdout.lock(), receiver, opt, true, label, None))
     })?;
-    Ok(code)
+    let success = exit_status.get().is_some_and(ExitStatus::success);
+    Ok((code, success))
+}
+
+/// Feeds a log previously captured with `--tee` back through the
+/// scrolling window, pacing lines at `opt.replay_speed` per second
+/// rather than delivering them all at once.
+fn run_replay(path: &Path, opt: &Opt) -> anyhow::Result<ExitCode> {
+    let file = std::fs::File::open(path)?;
+    let delay = replay_delay(opt.replay_speed);
+    thread::scope(|s| -> anyhow::Result<ExitCode> {
+        let (sender, receiver) = mpsc::channel();
+        spawn_resize_events(sender.clone())?;
+        spawn_tick_clock(sender.clone());
+        s.spawn(move || {
+            let reader = BufReader::new(file);
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                if sender.send(Event::Line(format!("{line}\n"))).is_err() {
+                    return;
+                }
+                thread::sleep(delay);
+            }
+            let _ = sender.send(Event::Eof);
+        });
+        let stdout = std::io::stdout();
+        Ok(print(stdout.lock(), receiver, opt, false, Some("replay"), None))
+    })
+}
+
+/// The delay between replayed lines for a given `--replay-speed` (lines
+/// per second). Clamps the speed away from zero (and below): dividing
+/// by `f64::MIN_POSITIVE` produces a number of seconds `Duration`
+/// can't represent and panics, so a `--replay-speed` of zero or less
+/// is instead clamped to 0.001 lines per second (one line every
+/// 1000 seconds).
+fn replay_delay(replay_speed: f64) -> std::time::Duration {
+    std::time::Duration::from_secs_f64(1.0 / replay_speed.max(0.001))
+}
+
+/// Prints the per-command status and duration, plus a grand total, once
+/// every queued command has finished.
+fn print_summary<W: std::io::Write>(
+    mut writer: W,
+    results: &[(&str, bool, std::time::Duration)],
+    total: std::time::Duration,
+) {
+    writeln!(writer, "╭─ summary").unwrap();
+    for (cmd, success, duration) in results {
+        let status = if *success { "ok    " } else { "failed" };
+        writeln!(writer, "│ {status} {:>9}  {cmd}", Format(*duration)).unwrap();
+    }
+    writeln!(writer, "╰─ total: {}", Format(total)).unwrap();
 }
 
-fn read<R>(reader: R, tx: mpsc::Sender<String>)
+fn exit_code_from_status(status: ExitStatus) -> ExitCode {
+    status
+        .code()
+        .and_then(|i| u8::try_from(i).ok())
+        .map(ExitCode::from)
+        .unwrap_or(if status.success() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        })
+}
+
+/// Reads raw chunks from `reader` as they arrive, sending each as an
+/// `Event::Line`. Several readers (e.g. stdout and stderr piped
+/// separately, or a PTY master) can share one `remaining` counter so
+/// only the last one to finish sends `Event::Eof`.
+///
+/// Reads whatever bytes are available rather than buffering up to the
+/// next `\n`: a progress bar that repaints a single line with bare `\r`
+/// never emits one, so line-buffering would sit frozen until EOF and
+/// then dump everything at once. `vt::Grid` is itself a byte-oriented
+/// state machine, so it doesn't need chunks aligned to line boundaries.
+fn read<R>(mut reader: R, tx: mpsc::Sender<Event>, remaining: Arc<AtomicUsize>)
 where
     R: std::io::Read,
 {
-    let stdout = BufReader::new(reader);
-    for line in stdout.lines() {
-        match line {
-            Ok(line) => tx.send(line).unwrap(),
-            Err(_) => break,
+    let mut buf = [0u8; 4096];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let chunk = String::from_utf8_lossy(&buf[..n]).into_owned();
+                if tx.send(Event::Line(chunk)).is_err() {
+                    return;
+                }
+            }
         }
     }
+    if remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+        let _ = tx.send(Event::Eof);
+    }
 }
 
-fn print<W>(mut writer: W, rx: mpsc::Receiver<String>, opt: Opt)
+/// Opens the `--tee` destination, if any. A file that can't be created
+/// (bad path, permissions) only disables teeing, rather than aborting
+/// the run the user is trying to watch.
+fn open_tee(opt: &Opt) -> Option<BufWriter<std::fs::File>> {
+    let path = opt.tee.as_ref()?;
+    match std::fs::OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => Some(BufWriter::new(file)),
+        Err(err) => {
+            eprintln!("scrollrun: couldn't open --tee file {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+/// Drives the scrolling window: feeds `Event::Line`s into the grid,
+/// resizes it on `Event::Resize`, and redraws on `Event::Tick`. Exits
+/// once every reader has hit `Event::Eof` and, if `expect_child` is set,
+/// `Event::ChildExit` has been observed too.
+///
+/// `pty_master`, when the child is running on a PTY, also gets every
+/// `Event::Resize` forwarded to it via `TIOCSWINSZ`, so the child sees
+/// the terminal's live size instead of the one it was spawned with.
+fn print<W>(
+    mut writer: W,
+    rx: mpsc::Receiver<Event>,
+    opt: &Opt,
+    expect_child: bool,
+    label: Option<&str>,
+    pty_master: Option<&std::fs::File>,
+) -> ExitCode
 where
     W: std::io::Write,
 {
-    const DELAY: std::time::Duration = std::time::Duration::from_millis(100);
     let start = Instant::now();
-    let mut output_lines = VecDeque::new();
-    let mut has_ended = false;
-    loop {
-        let num_lines = opt.num_lines().unwrap_or(10);
-        while let Ok(line) = rx.try_recv() {
-            output_lines.push_back(line);
-        }
-        write!(writer, "\x1B[2J\x1B[H").unwrap(); // clear
-        #[cfg(debug_assertions)]
-        write!(writer, "num lines: {num_lines:?} ").unwrap();
-        writeln!(writer, "· Elapsed time: {}", Format(start.elapsed())).unwrap();
-        writeln!(writer, "╭─").unwrap();
-        for line in output_lines.iter().take(num_lines) {
-            writeln!(writer, "│ {line}").unwrap();
-        }
-        writeln!(writer, "╰─").unwrap();
-        while output_lines.len() > num_lines {
-            has_ended = false;
-            output_lines.pop_front();
-        }
-        match rx.recv_timeout(DELAY) {
-            Err(mpsc::RecvTimeoutError::Disconnected) => {
-                if has_ended {
-                    break;
+    let mut rows = 24;
+    let mut num_lines = opt.num_lines(rows);
+    let mut grid = vt::Grid::new(80, num_lines);
+    let mut exit_code = None;
+    let mut eof = false;
+    let mut tee = open_tee(opt);
+    let alt_screen = AltScreen::enter();
+    for event in &rx {
+        match event {
+            Event::Line(chunk) => {
+                if let Some(tee) = &mut tee {
+                    let _ = tee.write_all(chunk.as_bytes());
+                }
+                grid.feed(chunk.as_bytes());
+            }
+            Event::Resize(cols, new_rows) => {
+                rows = new_rows;
+                num_lines = opt.num_lines(rows);
+                grid.resize(cols.into(), num_lines);
+                if let Some(master) = pty_master {
+                    let _ = pty::resize(master, cols, rows);
                 }
-                has_ended = true;
             }
-            Err(mpsc::RecvTimeoutError::Timeout) => continue,
-            Ok(line) => output_lines.push_back(line),
+            Event::Tick => draw(&mut writer, &grid, start, num_lines, label),
+            Event::ChildExit(code) => exit_code = Some(code),
+            Event::Eof => eof = true,
+        }
+        if eof && (!expect_child || exit_code.is_some()) {
+            break;
         }
-        thread::sleep(DELAY);
     }
+    // Leave the alternate screen before the final frame: that way the
+    // last frame and the "Finished in" line land on the real screen and
+    // stay there after the program exits, instead of vanishing with the
+    // alt screen along with everything else drawn during the run.
+    drop(alt_screen);
+    draw(&mut writer, &grid, start, num_lines, label);
     writeln!(writer, "· Finished in: {}", Format(start.elapsed())).unwrap();
+    exit_code.unwrap_or(ExitCode::SUCCESS)
+}
+
+/// Renders one frame of the scrolling window, with an optional label
+/// (e.g. "[2/3] npm test") for when several commands are queued.
+fn draw<W: std::io::Write>(
+    writer: &mut W,
+    grid: &vt::Grid,
+    start: Instant,
+    num_lines: usize,
+    label: Option<&str>,
+) {
+    write!(writer, "\x1B[2J\x1B[H").unwrap(); // clear
+    #[cfg(debug_assertions)]
+    write!(writer, "num lines: {num_lines:?} ").unwrap();
+    if let Some(label) = label {
+        writeln!(writer, "{label}").unwrap();
+    }
+    writeln!(writer, "· Elapsed time: {}", Format(start.elapsed())).unwrap();
+    writeln!(writer, "╭─").unwrap();
+    for line in grid.render() {
+        writeln!(writer, "│ {line}").unwrap();
+    }
+    writeln!(writer, "╰─").unwrap();
 }
 
 #[derive(Debug)]
@@ -220,32 +587,73 @@ fn print_completions(gen: Option<clap_complete::Shell>) -> bool {
 mod test {
     use textplots::Plot;
 
-    use super::{num_lines_heuristic, print, read, Format, Opt};
+    use super::{
+        batch_exit_code, num_lines_heuristic, open_tee, print, print_summary, read, replay_delay,
+        run_replay, truncate_tee, Event, Format, Opt,
+    };
+
+    use std::{
+        io::{Cursor, Read},
+        process::ExitCode,
+        sync::{atomic::AtomicUsize, mpsc, Arc},
+        thread,
+        time::Duration,
+    };
+
+    /// An `Opt` with every field at its default/disabled value, for tests
+    /// that only care about one or two fields.
+    fn test_opt() -> Opt {
+        Opt {
+            command: Vec::new(),
+            extra_commands: Vec::new(),
+            num_lines: Some(5),
+            generator: None,
+            pty: false,
+            tee: None,
+            replay: None,
+            replay_speed: 10.0,
+        }
+    }
 
-    use std::{io::Cursor, sync::mpsc, thread, time::Duration};
+    /// Concatenates the `Event::Line` chunks out of a finished `read()`,
+    /// asserting it ended with exactly one `Event::Eof`. `read()` no
+    /// longer splits on newlines, so this reassembles the raw bytes it
+    /// sent rather than asserting on how they were chunked.
+    fn output_of(rx: mpsc::Receiver<Event>) -> String {
+        let events: Vec<_> = rx.iter().collect();
+        let (chunks, eofs): (Vec<_>, Vec<_>) = events
+            .into_iter()
+            .partition(|e| matches!(e, Event::Line(_)));
+        assert_eq!(eofs.len(), 1, "expected exactly one Event::Eof");
+        chunks
+            .into_iter()
+            .map(|e| match e {
+                Event::Line(chunk) => chunk,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
 
     #[test]
-    fn single_line() {
+    fn single_chunk() {
         let input = "This is a single line";
         let reader = Cursor::new(input);
         let (tx, rx) = mpsc::channel();
 
-        read(reader, tx);
+        read(reader, tx, Arc::new(AtomicUsize::new(1)));
 
-        let result: Vec<String> = rx.iter().collect();
-        assert_eq!(result, vec!["This is a single line"]);
+        assert_eq!(output_of(rx), input);
     }
 
     #[test]
-    fn multiple_lines() {
+    fn multiple_lines_are_preserved_verbatim() {
         let input = "Line 1\nLine 2\nLine 3\n";
         let reader = Cursor::new(input);
         let (tx, rx) = mpsc::channel();
 
-        read(reader, tx);
+        read(reader, tx, Arc::new(AtomicUsize::new(1)));
 
-        let result: Vec<String> = rx.iter().collect();
-        assert_eq!(result, vec!["Line 1", "Line 2", "Line 3"]);
+        assert_eq!(output_of(rx), input);
     }
 
     #[test]
@@ -254,10 +662,9 @@ mod test {
         let reader = Cursor::new(input);
         let (tx, rx) = mpsc::channel();
 
-        read(reader, tx);
+        read(reader, tx, Arc::new(AtomicUsize::new(1)));
 
-        let result: Vec<String> = rx.iter().collect();
-        assert!(result.is_empty());
+        assert!(output_of(rx).is_empty());
     }
 
     #[test]
@@ -269,7 +676,7 @@ mod test {
 
         // Simulate an error by dropping the receiver in another thread
         let handle = thread::spawn(move || {
-            read(reader, tx);
+            read(reader, tx, Arc::new(AtomicUsize::new(1)));
         });
 
         // Drop the receiver to cause the sender to fail
@@ -278,15 +685,27 @@ mod test {
     }
 
     #[test]
-    fn mixed_newlines() {
-        let input = "Line 1\r\nLine 2\nLine 3\r\n";
+    fn carriage_returns_are_preserved_raw() {
+        let input = "Line 1\rLine 2\r\nLine 3";
         let reader = Cursor::new(input);
         let (tx, rx) = mpsc::channel();
 
-        read(reader, tx);
+        read(reader, tx, Arc::new(AtomicUsize::new(1)));
+
+        // Unlike `BufRead::lines`, `\r` isn't stripped: it's meaningful
+        // to the grid parser (it's how a progress bar repaints a line).
+        assert_eq!(output_of(rx), input);
+    }
+
+    #[test]
+    fn two_readers_send_a_single_eof() {
+        let (tx, rx) = mpsc::channel();
+        let remaining = Arc::new(AtomicUsize::new(2));
+
+        read(Cursor::new("a"), tx.clone(), remaining.clone());
+        read(Cursor::new("b"), tx, remaining);
 
-        let result: Vec<String> = rx.iter().collect();
-        assert_eq!(result, vec!["Line 1", "Line 2", "Line 3"]);
+        assert_eq!(output_of(rx), "ab");
     }
 
     #[test]
@@ -294,18 +713,19 @@ mod test {
         let (tx, rx) = mpsc::channel();
         let mut output = Cursor::new(Vec::new());
 
-        tx.send("Line 1".to_string()).unwrap();
-        tx.send("Line 2".to_string()).unwrap();
+        tx.send(Event::Resize(80, 24)).unwrap();
+        tx.send(Event::Line("Line 1".to_string())).unwrap();
+        tx.send(Event::Line("Line 2".to_string())).unwrap();
+        tx.send(Event::Eof).unwrap();
         drop(tx);
 
         print(
             &mut output,
             rx,
-            Opt {
-                command: None,
-                num_lines: Some(5),
-                generator: None,
-            },
+            &test_opt(),
+            false,
+            None,
+            None,
         );
 
         let output_str = String::from_utf8(output.into_inner()).unwrap();
@@ -319,19 +739,21 @@ mod test {
         let (tx, rx) = mpsc::channel();
         let mut output = Cursor::new(Vec::new());
 
+        tx.send(Event::Resize(80, 24)).unwrap();
         for i in 1..10 {
-            tx.send(format!("Line {}", i)).unwrap();
+            tx.send(Event::Line(format!("Line {}\n", i))).unwrap();
+            tx.send(Event::Tick).unwrap();
         }
+        tx.send(Event::Eof).unwrap();
         drop(tx);
 
         print(
             &mut output,
             rx,
-            Opt {
-                command: None,
-                num_lines: Some(5),
-                generator: None,
-            },
+            &test_opt(),
+            false,
+            None,
+            None,
         );
 
         let output_str = String::from_utf8(output.into_inner()).unwrap();
@@ -342,26 +764,31 @@ mod test {
     }
 
     #[test]
-    fn print_timeout() {
+    fn print_waits_for_child_exit() {
         let (tx, rx) = mpsc::channel();
         let mut output = Cursor::new(Vec::new());
 
-        tx.send("Line 1".to_string()).unwrap();
+        tx.send(Event::Resize(80, 24)).unwrap();
+        tx.send(Event::Line("Line 1".to_string())).unwrap();
+        tx.send(Event::Eof).unwrap();
+        let tx2 = tx.clone();
         thread::spawn(move || {
-            thread::sleep(Duration::from_millis(500));
-            tx.send("Line 2".to_string()).unwrap();
+            thread::sleep(Duration::from_millis(200));
+            tx2.send(Event::Line("Line 2".to_string())).unwrap();
+            tx2.send(Event::ChildExit(ExitCode::from(3))).unwrap();
         });
+        drop(tx);
 
-        print(
+        let code = print(
             &mut output,
             rx,
-            Opt {
-                command: None,
-                num_lines: Some(5),
-                generator: None,
-            },
+            &test_opt(),
+            true,
+            None,
+            None,
         );
 
+        assert_eq!(format!("{code:?}"), format!("{:?}", ExitCode::from(3)));
         let output_str = String::from_utf8(output.into_inner()).unwrap();
         assert!(output_str.contains("Line 1"));
         assert!(output_str.contains("Line 2"));
@@ -432,4 +859,157 @@ mod test {
             assert!(w[0] <= w[1])
         }
     }
+
+    #[test]
+    fn commands_chains_positional_then_extra() {
+        let opt = Opt {
+            command: vec!["first".to_string()],
+            extra_commands: vec!["second".to_string(), "third".to_string()],
+            ..test_opt()
+        };
+        assert_eq!(
+            opt.commands().collect::<Vec<_>>(),
+            vec!["first", "second", "third"]
+        );
+    }
+
+    #[test]
+    fn batch_exit_code_is_success_when_everything_succeeds() {
+        let codes = [(true, ExitCode::SUCCESS), (true, ExitCode::from(0))];
+        assert_eq!(
+            format!("{:?}", batch_exit_code(&codes)),
+            format!("{:?}", ExitCode::SUCCESS)
+        );
+    }
+
+    #[test]
+    fn batch_exit_code_is_the_first_failure_not_the_last_code() {
+        let codes = [
+            (true, ExitCode::SUCCESS),
+            (false, ExitCode::from(2)),
+            (true, ExitCode::SUCCESS),
+        ];
+        assert_eq!(
+            format!("{:?}", batch_exit_code(&codes)),
+            format!("{:?}", ExitCode::from(2))
+        );
+    }
+
+    #[test]
+    fn batch_exit_code_of_no_commands_is_success() {
+        assert_eq!(
+            format!("{:?}", batch_exit_code(&[])),
+            format!("{:?}", ExitCode::SUCCESS)
+        );
+    }
+
+    #[test]
+    fn print_summary_reports_ok_and_failed_commands() {
+        let mut output = Cursor::new(Vec::new());
+        let results = [
+            ("npm test", true, Duration::from_secs(5)),
+            ("npm build", false, Duration::from_secs(1)),
+        ];
+
+        print_summary(&mut output, &results, Duration::from_secs(6));
+
+        let output_str = String::from_utf8(output.into_inner()).unwrap();
+        assert!(output_str.contains("ok    "));
+        assert!(output_str.contains("npm test"));
+        assert!(output_str.contains("failed"));
+        assert!(output_str.contains("npm build"));
+        assert!(output_str.contains("total:"));
+    }
+
+    #[test]
+    fn replay_delay_is_the_inverse_of_the_speed() {
+        assert_eq!(replay_delay(10.0), Duration::from_millis(100));
+        assert_eq!(replay_delay(1.0), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn replay_delay_does_not_divide_by_zero() {
+        // A speed of 0 (or negative) must not panic; it clamps to the
+        // same small positive speed instead of dividing by near-zero.
+        assert_eq!(replay_delay(0.0), replay_delay(0.001));
+        assert_eq!(replay_delay(-5.0), replay_delay(0.001));
+    }
+
+    #[test]
+    fn open_tee_appends_rather_than_truncates() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("scrollrun-test-tee-{:?}", thread::current().id()));
+        std::fs::write(&path, "existing content\n").unwrap();
+
+        let opt = Opt { tee: Some(path.clone()), ..test_opt() };
+        {
+            let mut tee = open_tee(&opt).expect("tee file should open");
+            use std::io::Write;
+            tee.write_all(b"more content\n").unwrap();
+        }
+
+        let mut contents = String::new();
+        std::fs::File::open(&path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(contents, "existing content\nmore content\n");
+    }
+
+    #[test]
+    fn truncate_tee_empties_an_existing_file() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("scrollrun-test-truncate-{:?}", thread::current().id()));
+        std::fs::write(&path, "stale run from before\n").unwrap();
+
+        let opt = Opt { tee: Some(path.clone()), ..test_opt() };
+        truncate_tee(&opt);
+
+        let mut contents = String::new();
+        std::fs::File::open(&path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(contents.is_empty());
+    }
+
+    #[test]
+    fn truncate_tee_is_skipped_when_tee_and_replay_are_the_same_path() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("scrollrun-test-truncate-replay-{:?}", thread::current().id()));
+        std::fs::write(&path, "a captured run\n").unwrap();
+
+        let opt = Opt {
+            tee: Some(path.clone()),
+            replay: Some(path.clone()),
+            ..test_opt()
+        };
+        truncate_tee(&opt);
+
+        let mut contents = String::new();
+        std::fs::File::open(&path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(contents, "a captured run\n");
+    }
+
+    #[test]
+    fn run_replay_feeds_every_captured_line_back_through_print() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("scrollrun-test-replay-{:?}", thread::current().id()));
+        std::fs::write(&path, "Line 1\nLine 2\n").unwrap();
+
+        let opt = Opt { replay_speed: 1000.0, ..test_opt() };
+        let code = run_replay(&path, &opt).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(format!("{code:?}"), format!("{:?}", ExitCode::SUCCESS));
+    }
 }
@@ -0,0 +1,102 @@
+//! PTY-backed execution of the child command.
+//!
+//! Running `bash` behind a pipe makes most programs detect a non-tty
+//! stdout/stderr and disable colored output, spinners, and line
+//! buffering. Allocating a pseudo-terminal and running the child on its
+//! slave side keeps that behaviour intact; the merged output is then
+//! read back from the master side.
+
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::process::{Child, Command, ExitStatus, Stdio};
+
+use nix::pty::{openpty, Winsize};
+use nix::unistd::setsid;
+
+/// A child process running on the slave side of a pseudo-terminal.
+pub struct PtyChild {
+    child: Child,
+    master: std::fs::File,
+}
+
+impl PtyChild {
+    /// Spawns `bash --norc -c <command>` on a freshly allocated PTY sized
+    /// to `winsize`.
+    pub fn spawn(command: &str, winsize: Winsize) -> std::io::Result<Self> {
+        let pty = openpty(Some(&winsize), None).map_err(nix_to_io)?;
+        let slave_fd = pty.slave.as_raw_fd();
+
+        let mut cmd = Command::new("bash");
+        cmd.arg("--norc").arg("-c").arg(command);
+        cmd.stdin(dup_stdio(slave_fd)?);
+        cmd.stdout(dup_stdio(slave_fd)?);
+        cmd.stderr(dup_stdio(slave_fd)?);
+        // SAFETY: `setsid` and the `TIOCSCTTY` ioctl are both async-signal-safe
+        // and only touch the child's own process/terminal state.
+        unsafe {
+            cmd.pre_exec(move || {
+                setsid().map_err(nix_to_io)?;
+                if libc::ioctl(slave_fd, libc::TIOCSCTTY as _, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = cmd.spawn()?;
+        drop(pty.slave);
+        Ok(Self {
+            child,
+            master: std::fs::File::from(pty.master),
+        })
+    }
+
+    /// The master side of the PTY, carrying the child's merged output.
+    pub fn master(&self) -> std::io::Result<std::fs::File> {
+        self.master.try_clone()
+    }
+
+    pub fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        self.child.wait()
+    }
+}
+
+fn dup_stdio(fd: RawFd) -> std::io::Result<Stdio> {
+    let dup: OwnedFd = nix::unistd::dup(fd).map_err(nix_to_io)?;
+    Ok(Stdio::from(dup))
+}
+
+fn nix_to_io(err: nix::Error) -> std::io::Error {
+    std::io::Error::from_raw_os_error(err as i32)
+}
+
+/// Converts the current terminal size (falling back to 80x24) into the
+/// `Winsize` the PTY should be created with.
+pub fn current_winsize() -> Winsize {
+    let term = termsize::get();
+    Winsize {
+        ws_row: term.as_ref().map_or(24, |t| t.rows),
+        ws_col: term.as_ref().map_or(80, |t| t.cols),
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+/// Propagates a terminal resize to the PTY via its master fd. Without
+/// this, a child that queries its own terminal size after the user
+/// resizes their window (progress bars, `tput cols`) keeps acting on
+/// the size it was spawned with.
+pub fn resize(master: &std::fs::File, cols: u16, rows: u16) -> std::io::Result<()> {
+    let winsize = Winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let fd = master.as_raw_fd();
+    // SAFETY: `fd` is our own open master fd and `winsize` is a valid
+    // `Winsize` for the duration of this call; `TIOCSWINSZ` only updates
+    // the PTY's recorded size.
+    if unsafe { libc::ioctl(fd, libc::TIOCSWINSZ as _, &winsize) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,327 @@
+//! A small VT100-style terminal emulator.
+//!
+//! Child output isn't newline-delimited text: progress bars repaint a
+//! single line with `\r`, some tools move the cursor or clear lines with
+//! CSI sequences, and colors come in as SGR escapes. Feeding that
+//! straight into a `VecDeque<String>` scrollback loses all of it. `Grid`
+//! interprets the raw bytes into a fixed-size buffer of cells, the same
+//! way a real terminal would, so the framed output renders correctly.
+
+/// A single cell: the character it holds and the SGR sequence that was
+/// active when it was written, re-emitted on render so color survives.
+#[derive(Debug, Clone, Default)]
+pub struct Cell {
+    pub ch: char,
+    pub sgr: Option<String>,
+}
+
+/// A fixed-size grid of cells plus a cursor, fed raw bytes one chunk at a
+/// time and rendered a frame at a time.
+pub struct Grid {
+    width: usize,
+    height: usize,
+    rows: Vec<Vec<Cell>>,
+    cursor_row: usize,
+    cursor_col: usize,
+    current_sgr: Option<String>,
+    /// An escape sequence that started in a previous `feed()` call but
+    /// whose finalizer hadn't arrived yet, carried over so it can be
+    /// completed once the rest of it shows up in a later chunk.
+    pending_escape: Vec<u8>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        Self {
+            width,
+            height,
+            rows: vec![vec![Cell::default(); width]; height],
+            cursor_row: 0,
+            cursor_col: 0,
+            current_sgr: None,
+            pending_escape: Vec::new(),
+        }
+    }
+
+    /// Resizes the grid, preserving as much of the existing content as
+    /// fits. Called when a SIGWINCH event changes the terminal size.
+    ///
+    /// When shrinking, drops rows from the front (the oldest output),
+    /// the same direction `newline` scrolls in, so the most recently
+    /// written rows stay visible instead of the top of the scrollback.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let width = width.max(1);
+        let height = height.max(1);
+        for row in &mut self.rows {
+            row.resize(width, Cell::default());
+        }
+        if height < self.rows.len() {
+            let dropped = self.rows.len() - height;
+            self.rows.drain(0..dropped);
+            self.cursor_row = self.cursor_row.saturating_sub(dropped);
+        } else {
+            self.rows.resize(height, vec![Cell::default(); width]);
+        }
+        self.width = width;
+        self.height = height;
+        self.cursor_row = self.cursor_row.min(height - 1);
+        self.cursor_col = self.cursor_col.min(width - 1);
+    }
+
+    /// Feeds a chunk of raw bytes through the parser, mutating the grid
+    /// in place.
+    ///
+    /// `bytes` isn't guaranteed to start or end on an escape-sequence
+    /// boundary: a reader hands `feed()` whatever a single `read()`
+    /// syscall returned, so a `\x1B[32m` can arrive as `\x1B[` in one
+    /// chunk and `32m` in the next. A sequence left incomplete at the
+    /// end of `bytes` is stashed in `pending_escape` and completed once
+    /// the rest of it shows up here.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let data: std::borrow::Cow<[u8]> = if self.pending_escape.is_empty() {
+            bytes.into()
+        } else {
+            self.pending_escape.extend_from_slice(bytes);
+            std::mem::take(&mut self.pending_escape).into()
+        };
+        let mut i = 0;
+        while i < data.len() {
+            match data[i] {
+                b'\r' => {
+                    self.cursor_col = 0;
+                    i += 1;
+                }
+                b'\n' => {
+                    self.newline();
+                    i += 1;
+                }
+                0x1B => match self.parse_escape(&data[i..]) {
+                    Some(consumed) => i += consumed,
+                    None => {
+                        self.pending_escape = data[i..].to_vec();
+                        return;
+                    }
+                },
+                b => {
+                    self.put_char(b as char);
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    fn put_char(&mut self, ch: char) {
+        if self.cursor_col >= self.width {
+            self.newline();
+        }
+        self.rows[self.cursor_row][self.cursor_col] = Cell {
+            ch,
+            sgr: self.current_sgr.clone(),
+        };
+        self.cursor_col += 1;
+    }
+
+    fn newline(&mut self) {
+        self.cursor_col = 0;
+        if self.cursor_row + 1 < self.height {
+            self.cursor_row += 1;
+        } else {
+            self.rows.remove(0);
+            self.rows.push(vec![Cell::default(); self.width]);
+        }
+    }
+
+    /// Parses a single CSI/escape sequence starting at `bytes[0]` (the
+    /// `0x1B`). Returns the number of bytes it consumed, or `None` if
+    /// `bytes` ends before the sequence does — the caller is expected to
+    /// stash `bytes` and retry once more data arrives. Unrecognized
+    /// sequences are consumed and ignored.
+    fn parse_escape(&mut self, bytes: &[u8]) -> Option<usize> {
+        let Some(&next) = bytes.get(1) else {
+            return None; // don't yet know if this ESC starts a CSI sequence
+        };
+        if next != b'[' {
+            return Some(1); // not a CSI sequence; only the ESC is consumed
+        }
+        let mut i = 2;
+        while bytes.get(i).is_some_and(|b| b.is_ascii_digit() || *b == b';') {
+            i += 1;
+        }
+        let Some(&finalizer) = bytes.get(i) else {
+            return None; // finalizer hasn't arrived yet
+        };
+        let params = std::str::from_utf8(&bytes[2..i]).unwrap_or_default();
+        let nums: Vec<usize> = params
+            .split(';')
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| s.parse().ok())
+            .collect();
+        let arg = |default: usize| nums.first().copied().unwrap_or(default);
+        match finalizer {
+            b'A' => self.cursor_row = self.cursor_row.saturating_sub(arg(1)),
+            b'B' => self.cursor_row = (self.cursor_row + arg(1)).min(self.height - 1),
+            b'G' => self.cursor_col = arg(1).saturating_sub(1).min(self.width - 1),
+            b'H' => {
+                self.cursor_row = arg(1).saturating_sub(1).min(self.height - 1);
+                let col = nums.get(1).copied().unwrap_or(1);
+                self.cursor_col = col.saturating_sub(1).min(self.width - 1);
+            }
+            b'K' => self.erase_line(arg(0)),
+            b'm' => self.current_sgr = Some(format!("\x1B[{params}m")),
+            _ => {}
+        }
+        Some(i + 1)
+    }
+
+    fn erase_line(&mut self, mode: usize) {
+        let row = &mut self.rows[self.cursor_row];
+        let range = match mode {
+            0 => self.cursor_col..self.width,
+            1 => 0..self.cursor_col,
+            _ => 0..self.width,
+        };
+        for cell in &mut row[range] {
+            *cell = Cell::default();
+        }
+    }
+
+    /// Renders the visible rows, re-emitting each cell's SGR so colors
+    /// survive, with a reset at the end of any line whose last written
+    /// cell carries an SGR.
+    pub fn render(&self) -> Vec<String> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let mut line = String::new();
+                let mut active: Option<&str> = None;
+                for cell in row {
+                    if cell.ch == '\0' {
+                        continue;
+                    }
+                    if active != cell.sgr.as_deref() {
+                        if let Some(sgr) = &cell.sgr {
+                            line.push_str(sgr);
+                        } else {
+                            line.push_str("\x1B[0m");
+                        }
+                        active = cell.sgr.as_deref();
+                    }
+                    line.push(cell.ch);
+                }
+                if active.is_some() {
+                    line.push_str("\x1B[0m");
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Grid;
+
+    #[test]
+    fn plain_text() {
+        let mut grid = Grid::new(10, 2);
+        grid.feed(b"hi");
+        assert_eq!(grid.render()[0], "hi");
+    }
+
+    #[test]
+    fn carriage_return_overwrites_current_line() {
+        let mut grid = Grid::new(20, 1);
+        grid.feed(b"progress: 10%");
+        grid.feed(b"\rprogress: 50%");
+        assert_eq!(grid.render()[0], "progress: 50%");
+    }
+
+    #[test]
+    fn newline_advances_to_next_row() {
+        let mut grid = Grid::new(10, 2);
+        grid.feed(b"a\nb");
+        assert_eq!(grid.render(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn scrolls_up_when_output_exceeds_height() {
+        let mut grid = Grid::new(10, 2);
+        grid.feed(b"a\nb\nc");
+        assert_eq!(grid.render(), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn cursor_up_moves_up_n_rows() {
+        let mut grid = Grid::new(10, 3);
+        grid.feed(b"a\nb\nc");
+        grid.feed(b"\x1B[2AX"); // up 2 rows, same column (1, after `c`)
+        assert_eq!(grid.render()[0], "aX");
+    }
+
+    #[test]
+    fn cursor_column_move() {
+        let mut grid = Grid::new(10, 1);
+        grid.feed(b"hello");
+        grid.feed(b"\x1B[1GX"); // move to column 1 (0-indexed col 0)
+        assert_eq!(grid.render()[0], "Xello");
+    }
+
+    #[test]
+    fn erase_line_from_cursor_to_end() {
+        let mut grid = Grid::new(10, 1);
+        grid.feed(b"hello");
+        grid.feed(b"\x1B[3G\x1B[0K"); // move to col 3, erase to end
+        assert_eq!(grid.render()[0], "he");
+    }
+
+    #[test]
+    fn erase_line_from_start_to_cursor() {
+        let mut grid = Grid::new(10, 1);
+        grid.feed(b"hello");
+        grid.feed(b"\x1B[3G\x1B[1K"); // move to col 3, erase from start
+        assert_eq!(grid.render()[0], "llo");
+    }
+
+    #[test]
+    fn sgr_is_re_emitted_around_colored_text() {
+        let mut grid = Grid::new(10, 1);
+        grid.feed(b"\x1B[31mred");
+        assert_eq!(grid.render()[0], "\x1B[31mred\x1B[0m");
+    }
+
+    #[test]
+    fn escape_sequence_split_across_feed_calls_still_parses() {
+        let mut grid = Grid::new(10, 1);
+        // A reader delivers whatever a single read() returned, so a CSI
+        // sequence can be split anywhere across chunk boundaries.
+        grid.feed(b"\x1B[3");
+        grid.feed(b"1mred");
+        assert_eq!(grid.render()[0], "\x1B[31mred\x1B[0m");
+    }
+
+    #[test]
+    fn lone_escape_byte_split_across_feed_calls_still_parses() {
+        let mut grid = Grid::new(10, 1);
+        grid.feed(b"\x1B");
+        grid.feed(b"[31mred");
+        assert_eq!(grid.render()[0], "\x1B[31mred\x1B[0m");
+    }
+
+    #[test]
+    fn resize_shrink_keeps_most_recently_written_rows() {
+        let mut grid = Grid::new(10, 4);
+        grid.feed(b"a\nb\nc\nd");
+        grid.resize(10, 2);
+        assert_eq!(grid.render(), vec!["c", "d"]);
+    }
+
+    #[test]
+    fn resize_grow_preserves_existing_rows() {
+        let mut grid = Grid::new(10, 2);
+        grid.feed(b"a\nb");
+        grid.resize(10, 4);
+        assert_eq!(grid.render(), vec!["a", "b", "", ""]);
+    }
+}